@@ -0,0 +1,415 @@
+use super::{
+    commits_info::{get_commits_info, CommitInfo},
+    utils::repo,
+    CommitId,
+};
+use crate::error::{Error, Result};
+use git2::Oid;
+use scopetime::scope_time;
+use std::{collections::HashSet, process::Command};
+
+/// outcome of testing the commit currently under bisect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BisectTestResult {
+    ///
+    Good,
+    ///
+    Bad,
+    /// commit could not be tested, e.g. it does not build
+    Skip,
+}
+
+/// where a bisect session stands after a call to [`bisect_step`]
+#[derive(Debug)]
+pub enum BisectProgress {
+    /// more candidates remain; `next` has already been checked out and
+    /// should be tested
+    Next {
+        ///
+        next: CommitId,
+        /// number of commits still under suspicion
+        remaining: usize,
+    },
+    /// the bisect converged on a single, first-bad commit
+    Found(CommitInfo),
+}
+
+/// an in-progress bisect session, holding the still-undecided candidates in
+/// topological order (every candidate's ancestors, if still present, sort
+/// strictly before it; commit *time* is deliberately not used for ordering
+/// since it isn't guaranteed to be monotonic along the ancestry chain)
+#[derive(Debug, Clone)]
+pub struct BisectState {
+    repo_path: String,
+    candidates: Vec<CommitId>,
+}
+
+impl BisectState {
+    // the last candidate is always known bad (it is `bad` itself or the
+    // last commit that tested bad), so it is never picked for testing
+    fn midpoint(&self) -> Option<CommitId> {
+        self.candidates
+            .get(self.candidates.len().saturating_sub(1) / 2)
+            .copied()
+    }
+
+    fn into_progress(self) -> Result<(Self, BisectProgress)> {
+        if self.candidates.len() > 1 {
+            let remaining = self.candidates.len();
+            let next = self
+                .midpoint()
+                .expect("len > 1 implies a midpoint exists");
+
+            checkout_commit(&self.repo_path, next)?;
+
+            return Ok((self, BisectProgress::Next { next, remaining }));
+        }
+
+        let id = *self.candidates.first().ok_or_else(|| {
+            Error::Generic(String::from(
+                "bisect: all remaining candidates were skipped, \
+                 cannot narrow further",
+            ))
+        })?;
+
+        let info = get_commits_info(&self.repo_path, &[id.get_oid()], 100)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::Generic(String::from(
+                    "bisect: culprit commit could not be read back",
+                ))
+            })?;
+
+        Ok((
+            Self { candidates: Vec::new(), ..self },
+            BisectProgress::Found(info),
+        ))
+    }
+}
+
+/// start a bisect session: collects every commit reachable from `bad` down
+/// to (but excluding) `good` as a candidate, checks out the midpoint and
+/// returns the session together with the first candidate to test; when the
+/// range holds a single commit, nothing is checked out and that commit is
+/// returned as [`BisectProgress::Found`]
+pub fn bisect_start(
+    repo_path: &str,
+    good: CommitId,
+    bad: CommitId,
+) -> Result<(BisectState, BisectProgress)> {
+    scope_time!("bisect_start");
+
+    let repository = repo(repo_path)?;
+
+    if good == bad {
+        return Err(Error::Generic(String::from(
+            "bisect: `good` and `bad` are the same commit",
+        )));
+    }
+
+    if !repository
+        .graph_descendant_of(bad.get_oid(), good.get_oid())?
+    {
+        return Err(Error::Generic(String::from(
+            "bisect: `good` is not an ancestor of `bad`",
+        )));
+    }
+
+    let candidates =
+        collect_range(&repository, good.get_oid(), bad.get_oid())?;
+
+    let state = BisectState {
+        repo_path: repo_path.to_string(),
+        candidates,
+    };
+
+    state.into_progress()
+}
+
+/// run `command` in a shell against the commit currently under test (checked
+/// out by the previous call), classify the exit status, narrow the candidate
+/// range accordingly and check out the next commit to test
+pub fn bisect_step(
+    state: BisectState,
+    command: &str,
+    skip_exit_code: i32,
+) -> Result<(BisectState, BisectProgress)> {
+    scope_time!("bisect_step");
+
+    let tested = state.midpoint().ok_or_else(|| {
+        Error::Generic(String::from("bisect: no commit under test"))
+    })?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(&state.repo_path)
+        .status()
+        .map_err(|e| {
+            Error::Generic(format!(
+                "bisect: failed to run test command: {}",
+                e
+            ))
+        })?;
+
+    let result = match status.code() {
+        Some(0) => BisectTestResult::Good,
+        Some(code) if code == skip_exit_code => {
+            BisectTestResult::Skip
+        }
+        _ => BisectTestResult::Bad,
+    };
+
+    let tested_index = state
+        .candidates
+        .iter()
+        .position(|id| *id == tested)
+        .expect("tested commit is always a current candidate");
+
+    let candidates = match result {
+        // every candidate topologically before (and including) the tested
+        // commit is an ancestor of a known-good commit, hence good too
+        BisectTestResult::Good => {
+            state.candidates[tested_index + 1..].to_vec()
+        }
+        // the culprit is the tested commit or one of its ancestors
+        BisectTestResult::Bad => {
+            state.candidates[..=tested_index].to_vec()
+        }
+        // drop only the untestable commit
+        BisectTestResult::Skip => state
+            .candidates
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| *i != tested_index)
+            .map(|(_, id)| id)
+            .collect(),
+    };
+
+    BisectState { candidates, ..state }.into_progress()
+}
+
+fn checkout_commit(repo_path: &str, id: CommitId) -> Result<()> {
+    let repository = repo(repo_path)?;
+    let commit = repository.find_commit(id.get_oid())?;
+
+    repository.checkout_tree(commit.as_object(), None)?;
+    repository.set_head_detached(id.get_oid())?;
+
+    Ok(())
+}
+
+/// every commit reachable from `start` (inclusive), by repeated parent
+/// closure
+fn reachable(
+    repository: &git2::Repository,
+    start: Oid,
+) -> Result<HashSet<Oid>> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![start];
+
+    while let Some(id) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        for parent in repository.find_commit(id)?.parent_ids() {
+            stack.push(parent);
+        }
+    }
+
+    Ok(seen)
+}
+
+/// implements `good..bad` (`reachable(bad) - reachable(good)`): every
+/// commit reachable from `bad` that is not also reachable from `good`.
+/// excluding only the single `good` id is not enough - a merge commit in
+/// `bad`'s history can reach a shared ancestor of `good` through a side
+/// that never passes through `good` itself, so the full ancestor set of
+/// `good` has to be excluded, not just `good`
+fn commits_in_range(
+    repository: &git2::Repository,
+    good: Oid,
+    bad: Oid,
+) -> Result<HashSet<Oid>> {
+    let excluded = reachable(repository, good)?;
+
+    let mut in_range = HashSet::new();
+    let mut stack = vec![bad];
+
+    while let Some(id) = stack.pop() {
+        if excluded.contains(&id) || !in_range.insert(id) {
+            continue;
+        }
+
+        for parent in repository.find_commit(id)?.parent_ids() {
+            stack.push(parent);
+        }
+    }
+
+    Ok(in_range)
+}
+
+/// walks every ancestor of `bad` down to but excluding `good`, returning the
+/// candidates in topological (ancestor-before-descendant) order; commits
+/// are not ordered by commit time since it is not guaranteed to be
+/// monotonic along the ancestry chain (rebases, amends, cherry-picks,
+/// clock skew, or several commits landing in the same wall-clock second)
+fn collect_range(
+    repository: &git2::Repository,
+    good: Oid,
+    bad: Oid,
+) -> Result<Vec<CommitId>> {
+    let in_range = commits_in_range(repository, good, bad)?;
+
+    let mut order = Vec::with_capacity(in_range.len());
+    let mut visited = HashSet::new();
+    let mut stack: Vec<(Oid, bool)> =
+        in_range.iter().map(|id| (*id, false)).collect();
+
+    while let Some((id, expanded)) = stack.pop() {
+        if expanded {
+            order.push(CommitId::new(id));
+            continue;
+        }
+
+        if !visited.insert(id) {
+            continue;
+        }
+
+        // re-push as "expanded" so it is emitted only once every one of
+        // its in-range parents has already been emitted
+        stack.push((id, true));
+
+        for parent in repository.find_commit(id)?.parent_ids() {
+            if in_range.contains(&parent) {
+                stack.push((parent, false));
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bisect_start, bisect_step, collect_range, BisectProgress};
+    use crate::{
+        error::Result,
+        sync::{
+            commit, stage_add_file, tests::repo_init_empty, CommitId,
+        },
+    };
+    use std::{collections::HashSet, fs::File, io::Write, path::Path};
+
+    fn commit_n(repo_path: &str, root: &Path, n: usize) -> CommitId {
+        let file_path = Path::new("foo");
+        File::create(&root.join(file_path))
+            .unwrap()
+            .write_all(format!("{}", n).as_bytes())
+            .unwrap();
+        stage_add_file(repo_path, file_path).unwrap();
+        CommitId::new(
+            commit(repo_path, &format!("commit{}", n)).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_bisect_finds_culprit() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let good = commit_n(repo_path, root, 0);
+        let _c1 = commit_n(repo_path, root, 1);
+        let culprit = commit_n(repo_path, root, 2);
+        let bad = commit_n(repo_path, root, 3);
+
+        let (mut state, mut progress) =
+            bisect_start(repo_path, good, bad).unwrap();
+
+        let found = loop {
+            match progress {
+                BisectProgress::Found(info) => break info,
+                BisectProgress::Next { next, .. } => {
+                    let command = if next == culprit || next == bad {
+                        "exit 1"
+                    } else {
+                        "exit 0"
+                    };
+                    let (s, p) =
+                        bisect_step(state, command, 125).unwrap();
+                    state = s;
+                    progress = p;
+                }
+            }
+        };
+
+        assert_eq!(found.id, culprit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bisect_rejects_equal_range() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let only = commit_n(repo_path, root, 0);
+
+        assert!(bisect_start(repo_path, only, only).is_err());
+
+        Ok(())
+    }
+
+    // R -> {G, Z}, G -> A, Z -> B, BAD = merge(A, B). `R` is an ancestor of
+    // `good` (via `G`) but is *not* reached by literally walking through
+    // the `good` node on `bad`'s side (`B -> Z -> R`), so a check that only
+    // excludes the single `good` id would wrongly pull `R` (and anything
+    // behind it) into the candidate range.
+    #[test]
+    fn test_collect_range_excludes_good_ancestors() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+
+        let sig = repo.signature().unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let make = |msg: &str, parents: &[&git2::Commit]| {
+            repo.commit(None, &sig, &sig, msg, &tree, parents)
+                .unwrap()
+        };
+
+        let r = make("R", &[]);
+        let r_commit = repo.find_commit(r).unwrap();
+
+        let g = make("G", &[&r_commit]);
+        let g_commit = repo.find_commit(g).unwrap();
+
+        let z = make("Z", &[&r_commit]);
+        let z_commit = repo.find_commit(z).unwrap();
+
+        let a = make("A", &[&g_commit]);
+        let a_commit = repo.find_commit(a).unwrap();
+
+        let b = make("B", &[&z_commit]);
+        let b_commit = repo.find_commit(b).unwrap();
+
+        let bad = make("BAD", &[&a_commit, &b_commit]);
+
+        let candidates = collect_range(&repo, g, bad)?;
+        let ids: HashSet<_> =
+            candidates.into_iter().map(CommitId::get_oid).collect();
+
+        assert!(!ids.contains(&r), "R is an ancestor of good");
+        assert!(!ids.contains(&g), "good itself is never a candidate");
+        assert!(ids.contains(&z));
+        assert!(ids.contains(&a));
+        assert!(ids.contains(&b));
+        assert!(ids.contains(&bad));
+
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
+use super::logwalker::{diff_contains_file, LogWalker};
 use super::utils::repo;
 use crate::error::Result;
 use git2::{Commit, Error, Oid};
 use scopetime::scope_time;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// identifies a single commit
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -38,6 +40,63 @@ pub struct CommitInfo {
     pub id: CommitId,
 }
 
+impl CommitInfo {
+    /// human friendly rendering of `self.time` relative to now, e.g.
+    /// "3 minutes ago", "yesterday", "2 years ago"
+    pub fn time_to_string(&self) -> String {
+        time_to_string(self.time, now_timestamp())
+    }
+}
+
+fn now_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn time_to_string(time: i64, now: i64) -> String {
+    let delta = now - time;
+    let future = delta < 0;
+    let delta = delta.abs();
+
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = MINUTE * 60;
+    const DAY: i64 = HOUR * 24;
+    const WEEK: i64 = DAY * 7;
+    const MONTH: i64 = DAY * 30;
+    const YEAR: i64 = DAY * 365;
+
+    if delta < MINUTE {
+        return String::from("just now");
+    }
+
+    let (value, unit) = if delta < HOUR {
+        (delta / MINUTE, "minute")
+    } else if delta < DAY {
+        (delta / HOUR, "hour")
+    } else if delta < WEEK {
+        (delta / DAY, "day")
+    } else if delta < MONTH {
+        (delta / WEEK, "week")
+    } else if delta < YEAR {
+        (delta / MONTH, "month")
+    } else {
+        (delta / YEAR, "year")
+    };
+
+    if !future && unit == "day" && value == 1 {
+        return String::from("yesterday");
+    }
+
+    let plural = if value == 1 { "" } else { "s" };
+
+    if future {
+        format!("in {} {}{}", value, unit, plural)
+    } else {
+        format!("{} {}{} ago", value, unit, plural)
+    }
+}
+
 ///
 pub fn get_commits_info(
     repo_path: &str,
@@ -55,25 +114,84 @@ pub fn get_commits_info(
         .into_iter();
 
     let res = commits
-        .map(|c: Commit| {
-            let message = get_message(&c, message_length_limit);
-            let author = if let Some(name) = c.author().name() {
-                String::from(name)
-            } else {
-                String::from("<unknown>")
-            };
-            CommitInfo {
-                message,
-                author,
-                time: c.time().seconds(),
-                id: CommitId(c.id()),
-            }
-        })
+        .map(|c: Commit| commit_to_info(&c, message_length_limit))
         .collect::<Vec<_>>();
 
     Ok(res)
 }
 
+/// default commit message length used when a caller does not care to limit it
+const DEFAULT_MESSAGE_LENGTH_LIMIT: usize = 100;
+
+/// opaque resume point returned by [`get_commits_page`], fed back in as
+/// `after` on the following call. Carries the whole walk frontier (every
+/// commit discovered but not yet popped), not just the last emitted
+/// commit's direct parents - the direct-parents-only shortcut loses a
+/// merge's other-side branch whenever it is still sitting unpopped in the
+/// walker when a page's `count` limit is hit
+#[derive(Debug, Clone, Default)]
+pub struct CommitsPageCursor(Vec<CommitId>);
+
+/// walks history (optionally scoped to `path`) and returns at most `count`
+/// `CommitInfo`s starting right after `after` (exclusive), together with the
+/// cursor to pass as `after` for the next page
+pub fn get_commits_page(
+    repo_path: &str,
+    count: usize,
+    after: Option<CommitsPageCursor>,
+    path: Option<String>,
+) -> Result<(Vec<CommitInfo>, Option<CommitsPageCursor>)> {
+    scope_time!("get_commits_page");
+
+    let repo = repo(repo_path)?;
+
+    let mut walker =
+        LogWalker::new(&repo).filter(path.map(diff_contains_file));
+
+    match after {
+        Some(cursor) => walker.start(&cursor.0)?,
+        None => walker.start(&[CommitId::new(
+            repo.head()?.peel_to_commit()?.id(),
+        )])?,
+    }
+
+    let mut ids = Vec::with_capacity(count);
+    walker.read(&mut ids, count)?;
+
+    let frontier = walker.frontier();
+    let next_cursor = if frontier.is_empty() {
+        None
+    } else {
+        Some(CommitsPageCursor(frontier))
+    };
+
+    let info = ids
+        .iter()
+        .map(|id| repo.find_commit(id.get_oid()))
+        .collect::<std::result::Result<Vec<Commit>, Error>>()?
+        .iter()
+        .map(|c| commit_to_info(c, DEFAULT_MESSAGE_LENGTH_LIMIT))
+        .collect::<Vec<_>>();
+
+    Ok((info, next_cursor))
+}
+
+fn commit_to_info(c: &Commit, message_length_limit: usize) -> CommitInfo {
+    let message = get_message(c, message_length_limit);
+    let author = if let Some(name) = c.author().name() {
+        String::from(name)
+    } else {
+        String::from("<unknown>")
+    };
+
+    CommitInfo {
+        message,
+        author,
+        time: c.time().seconds(),
+        id: CommitId(c.id()),
+    }
+}
+
 fn get_message(c: &Commit, message_length_limit: usize) -> String {
     if let Some(msg) = c.message() {
         limit_str(msg, message_length_limit)
@@ -93,7 +211,7 @@ fn limit_str(s: &str, limit: usize) -> String {
 #[cfg(test)]
 mod tests {
 
-    use super::get_commits_info;
+    use super::{get_commits_info, get_commits_page};
     use crate::error::Result;
     use crate::sync::{
         commit, stage_add_file, tests::repo_init_empty,
@@ -124,4 +242,126 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_commits_page() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path).unwrap();
+        let c1 = commit(repo_path, "commit1").unwrap();
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path).unwrap();
+        let c2 = commit(repo_path, "commit2").unwrap();
+
+        let (page1, cursor) =
+            get_commits_page(repo_path, 1, None, None).unwrap();
+
+        assert_eq!(page1.len(), 1);
+        assert_eq!(page1[0].id.get_oid(), c2);
+        assert!(cursor.is_some());
+
+        let (page2, cursor) =
+            get_commits_page(repo_path, 1, cursor, None).unwrap();
+
+        assert_eq!(page2.len(), 1);
+        assert_eq!(page2[0].id.get_oid(), c1);
+        assert!(cursor.is_none());
+
+        Ok(())
+    }
+
+    // reproduces a history shaped like
+    //   T -> M -> X -> X1
+    //          \-> Y
+    // with a page size of 1: by the time `M` is popped, both `X` and `Y`
+    // are discovered but only `X` gets popped before the page fills up.
+    // the cursor must still be able to resume into `Y`'s branch on a later
+    // page instead of silently dropping it.
+    #[test]
+    fn test_get_commits_page_keeps_merge_branch() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let sig = repo.signature().unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+
+        let make = |msg: &str, parents: &[&git2::Commit]| {
+            repo.commit(None, &sig, &sig, msg, &tree, parents)
+                .unwrap()
+        };
+
+        let y = make("Y", &[]);
+        let y_commit = repo.find_commit(y).unwrap();
+
+        let x1 = make("X1", &[]);
+        let x1_commit = repo.find_commit(x1).unwrap();
+
+        let x = make("X", &[&x1_commit]);
+        let x_commit = repo.find_commit(x).unwrap();
+
+        let m = make("M", &[&x_commit, &y_commit]);
+        let m_commit = repo.find_commit(m).unwrap();
+
+        let t = make("T", &[&m_commit]);
+
+        repo.set_head_detached(t).unwrap();
+
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = None;
+
+        loop {
+            let (page, next) =
+                get_commits_page(repo_path, 1, cursor, None)
+                    .unwrap();
+
+            for info in &page {
+                seen.insert(info.id.get_oid());
+            }
+
+            match next {
+                Some(c) => cursor = Some(c),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen.len(), 5);
+        assert!(seen.contains(&y));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_to_string() {
+        use super::time_to_string;
+
+        const MINUTE: i64 = 60;
+        const HOUR: i64 = MINUTE * 60;
+        const DAY: i64 = HOUR * 24;
+        const YEAR: i64 = DAY * 365;
+
+        let now = 10 * YEAR;
+
+        assert_eq!(time_to_string(now, now), "just now");
+        assert_eq!(time_to_string(now - 30, now), "just now");
+        assert_eq!(
+            time_to_string(now - 3 * MINUTE, now),
+            "3 minutes ago"
+        );
+        assert_eq!(time_to_string(now - HOUR, now), "1 hour ago");
+        assert_eq!(time_to_string(now - DAY, now), "yesterday");
+        assert_eq!(
+            time_to_string(now - 2 * YEAR, now),
+            "2 years ago"
+        );
+        assert_eq!(
+            time_to_string(now + 5 * MINUTE, now),
+            "in 5 minutes"
+        );
+    }
 }
@@ -0,0 +1,224 @@
+use super::CommitId;
+use crate::error::Result;
+use git2::{Commit, Repository};
+use scopetime::scope_time;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+    sync::Arc,
+};
+
+/// wraps a `Commit` so it can be ordered in a `BinaryHeap` by commit time,
+/// newest first
+struct TimeOrderedCommit<'a>(Commit<'a>);
+
+impl<'a> Eq for TimeOrderedCommit<'a> {}
+
+impl<'a> PartialEq for TimeOrderedCommit<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.time().eq(&other.0.time())
+    }
+}
+
+impl<'a> PartialOrd for TimeOrderedCommit<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Ord for TimeOrderedCommit<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.time().cmp(&other.0.time())
+    }
+}
+
+/// predicate consulted before a commit is emitted by [`LogWalker`].
+/// returning `false` skips the commit but its parents are still traversed.
+pub type LogFilter = Arc<
+    Box<
+        dyn Fn(&Repository, &CommitId) -> Result<bool>
+            + Send
+            + Sync,
+    >,
+>;
+
+/// filter that only lets commits through whose diff touches `file_path`
+pub fn diff_contains_file(file_path: String) -> LogFilter {
+    Arc::new(Box::new(
+        move |repo: &Repository, commit_id: &CommitId| -> Result<bool> {
+            let diff = get_commit_diff(repo, *commit_id)?;
+
+            Ok(diff.deltas().any(|delta| {
+                delta
+                    .old_file()
+                    .path()
+                    .map(|p| p.to_string_lossy() == file_path.as_str())
+                    .unwrap_or_default()
+                    || delta
+                        .new_file()
+                        .path()
+                        .map(|p| {
+                            p.to_string_lossy() == file_path.as_str()
+                        })
+                        .unwrap_or_default()
+            }))
+        },
+    ))
+}
+
+fn get_commit_diff<'a>(
+    repo: &'a Repository,
+    id: CommitId,
+) -> Result<git2::Diff<'a>> {
+    let commit = repo.find_commit(id.get_oid())?;
+    let commit_tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+
+    let diff = repo.diff_tree_to_tree(
+        parent_tree.as_ref(),
+        Some(&commit_tree),
+        None,
+    )?;
+
+    Ok(diff)
+}
+
+/// lazily walks commit history starting from a set of tips, newest commit
+/// first, yielding at most `limit` ids per call to [`LogWalker::read`]
+pub struct LogWalker<'a> {
+    repo: &'a Repository,
+    commits: BinaryHeap<TimeOrderedCommit<'a>>,
+    visited: HashSet<git2::Oid>,
+    filter: Option<LogFilter>,
+}
+
+impl<'a> LogWalker<'a> {
+    ///
+    pub fn new(repo: &'a Repository) -> Self {
+        Self {
+            repo,
+            commits: BinaryHeap::new(),
+            visited: HashSet::new(),
+            filter: None,
+        }
+    }
+
+    /// attach a filter, see [`diff_contains_file`]
+    #[must_use]
+    pub fn filter(self, filter: Option<LogFilter>) -> Self {
+        Self { filter, ..self }
+    }
+
+    /// ids of commits discovered but not yet popped/emitted. This is the
+    /// full resume frontier, not just the last emitted commit's parents -
+    /// a merge's other-side parent can still be sitting unpopped here even
+    /// after its sibling branch has already been emitted, so callers that
+    /// pause a walk (e.g. for pagination) must carry this whole set
+    /// forward as the next `start()` tips, not merely the last id's
+    /// parents, or that branch is silently dropped
+    pub fn frontier(&self) -> Vec<CommitId> {
+        self.commits
+            .iter()
+            .map(|tc| CommitId::new(tc.0.id()))
+            .collect()
+    }
+
+    /// seed the walk with `tips` (e.g. `HEAD`), skipping ones already seen
+    pub fn start(&mut self, tips: &[CommitId]) -> Result<()> {
+        for tip in tips {
+            if self.visited.insert(tip.get_oid()) {
+                let commit = self.repo.find_commit(tip.get_oid())?;
+                self.commits.push(TimeOrderedCommit(commit));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// pop commits off the heap until `limit` of them have been emitted or
+    /// the heap is empty, pushing each unvisited parent back on, and
+    /// appending emitted (filter-accepted) ids to `out`
+    pub fn read(
+        &mut self,
+        out: &mut Vec<CommitId>,
+        limit: usize,
+    ) -> Result<usize> {
+        scope_time!("read");
+
+        let mut count = 0_usize;
+
+        if limit == 0 {
+            return Ok(count);
+        }
+
+        while let Some(TimeOrderedCommit(commit)) = self.commits.pop()
+        {
+            for parent in commit.parents() {
+                if self.visited.insert(parent.id()) {
+                    self.commits.push(TimeOrderedCommit(parent));
+                }
+            }
+
+            let id = CommitId::new(commit.id());
+            let is_match = self
+                .filter
+                .as_ref()
+                .map_or(Ok(true), |filter| filter(self.repo, &id))?;
+
+            if is_match {
+                out.push(id);
+                count += 1;
+            }
+
+            if count == limit {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogWalker;
+    use crate::{
+        error::Result,
+        sync::{
+            commit, stage_add_file, tests::repo_init_empty, utils::repo,
+            CommitId,
+        },
+    };
+    use std::{fs::File, io::Write, path::Path};
+
+    #[test]
+    fn test_logwalker() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo_handle) = repo_init_empty().unwrap();
+        let root = repo_handle.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path).unwrap();
+        let c1 = commit(repo_path, "commit1").unwrap();
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path).unwrap();
+        let c2 = commit(repo_path, "commit2").unwrap();
+
+        let r = repo(repo_path)?;
+        let mut walker = LogWalker::new(&r);
+        walker.start(&[CommitId::new(c2)])?;
+
+        let mut out = Vec::new();
+        let count = walker.read(&mut out, 100)?;
+
+        assert_eq!(count, 2);
+        assert_eq!(out, vec![CommitId::new(c2), CommitId::new(c1)]);
+
+        Ok(())
+    }
+}